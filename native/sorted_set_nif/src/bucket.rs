@@ -0,0 +1,171 @@
+use crate::configuration::SetOrdering;
+use crate::supported_term::SupportedTerm;
+
+#[derive(Debug, PartialEq)]
+pub enum BucketAddResult {
+    Added(usize),
+    Duplicate(usize),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BucketRemoveResult {
+    Removed(usize),
+    NotFound,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BucketFindResult {
+    Found(usize),
+    NotFound { insertion_index: usize },
+}
+
+#[derive(Debug)]
+pub struct Bucket {
+    inner: Vec<SupportedTerm>,
+}
+
+impl Bucket {
+    pub fn new(capacity: usize) -> Self {
+        Bucket {
+            inner: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn from_vec(inner: Vec<SupportedTerm>) -> Self {
+        Bucket { inner }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn first(&self) -> Option<&SupportedTerm> {
+        self.inner.first()
+    }
+
+    pub fn last(&self) -> Option<&SupportedTerm> {
+        self.inner.last()
+    }
+
+    pub fn at(&self, index: usize) -> Option<&SupportedTerm> {
+        self.inner.get(index)
+    }
+
+    pub fn to_vec(&self) -> Vec<SupportedTerm> {
+        self.inner.clone()
+    }
+
+    pub fn add(&mut self, item: SupportedTerm, ordering: &SetOrdering) -> BucketAddResult {
+        match self.find_index(&item, ordering) {
+            BucketFindResult::Found(idx) => BucketAddResult::Duplicate(idx),
+            BucketFindResult::NotFound { insertion_index } => {
+                self.inner.insert(insertion_index, item);
+                BucketAddResult::Added(insertion_index)
+            }
+        }
+    }
+
+    pub fn remove(&mut self, item: &SupportedTerm, ordering: &SetOrdering) -> BucketRemoveResult {
+        match self.find_index(item, ordering) {
+            BucketFindResult::Found(idx) => {
+                self.inner.remove(idx);
+                BucketRemoveResult::Removed(idx)
+            }
+            BucketFindResult::NotFound { .. } => BucketRemoveResult::NotFound,
+        }
+    }
+
+    pub fn find_index(&self, item: &SupportedTerm, ordering: &SetOrdering) -> BucketFindResult {
+        match self
+            .inner
+            .binary_search_by(|candidate| ordering.compare(candidate, item))
+        {
+            Ok(idx) => BucketFindResult::Found(idx),
+            Err(idx) => BucketFindResult::NotFound {
+                insertion_index: idx,
+            },
+        }
+    }
+
+    // Splits off the back half of this bucket into a new one, keeping
+    // both halves sorted and under `max_bucket_size` after an overflow.
+    pub fn split(&mut self) -> Bucket {
+        let split_at = self.inner.len() / 2;
+        Bucket {
+            inner: self.inner.split_off(split_at),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::supported_term::DEFAULT_CLASS_PRIORITY;
+
+    fn ascending() -> SetOrdering {
+        SetOrdering {
+            direction: crate::configuration::Direction::Ascending,
+            class_priority: DEFAULT_CLASS_PRIORITY.to_vec(),
+        }
+    }
+
+    #[test]
+    fn add_keeps_elements_sorted_and_reports_insertion_index() {
+        let ordering = ascending();
+        let mut bucket = Bucket::new(4);
+
+        assert_eq!(
+            bucket.add(SupportedTerm::Integer(5), &ordering),
+            BucketAddResult::Added(0)
+        );
+        assert_eq!(
+            bucket.add(SupportedTerm::Integer(1), &ordering),
+            BucketAddResult::Added(0)
+        );
+        assert_eq!(
+            bucket.add(SupportedTerm::Integer(5), &ordering),
+            BucketAddResult::Duplicate(1)
+        );
+        assert_eq!(
+            bucket.to_vec(),
+            vec![SupportedTerm::Integer(1), SupportedTerm::Integer(5)]
+        );
+    }
+
+    #[test]
+    fn remove_reports_not_found_for_a_missing_item() {
+        let ordering = ascending();
+        let mut bucket = Bucket::new(4);
+        bucket.add(SupportedTerm::Integer(1), &ordering);
+
+        assert_eq!(
+            bucket.remove(&SupportedTerm::Integer(2), &ordering),
+            BucketRemoveResult::NotFound
+        );
+        assert_eq!(
+            bucket.remove(&SupportedTerm::Integer(1), &ordering),
+            BucketRemoveResult::Removed(0)
+        );
+        assert!(bucket.is_empty());
+    }
+
+    #[test]
+    fn split_divides_elements_between_both_halves() {
+        let ordering = ascending();
+        let mut bucket = Bucket::new(4);
+        for value in [1, 2, 3, 4] {
+            bucket.add(SupportedTerm::Integer(value), &ordering);
+        }
+
+        let back_half = bucket.split();
+
+        assert_eq!(bucket.len(), 2);
+        assert_eq!(back_half.len(), 2);
+        assert_eq!(bucket.last(), Some(&SupportedTerm::Integer(2)));
+        assert_eq!(back_half.first(), Some(&SupportedTerm::Integer(3)));
+    }
+}
@@ -0,0 +1,46 @@
+use crate::supported_term::{SupportedTerm, TermClass, DEFAULT_CLASS_PRIORITY};
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+// How a `SortedSet` orders its elements: a direction, plus a priority
+// among term classes used to break ties between terms of different
+// variants (e.g. an integer against an atom). Every comparison site in
+// `SortedSet`/`Bucket` goes through `compare` rather than `SupportedTerm`'s
+// own `Ord` impl, so flipping `direction` reorders the whole set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetOrdering {
+    pub direction: Direction,
+    pub class_priority: Vec<TermClass>,
+}
+
+impl SetOrdering {
+    pub fn compare(&self, a: &SupportedTerm, b: &SupportedTerm) -> Ordering {
+        let natural = a.compare_with_priority(b, &self.class_priority);
+
+        match self.direction {
+            Direction::Ascending => natural,
+            Direction::Descending => natural.reverse(),
+        }
+    }
+}
+
+impl Default for SetOrdering {
+    fn default() -> Self {
+        SetOrdering {
+            direction: Direction::Ascending,
+            class_priority: DEFAULT_CLASS_PRIORITY.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Configuration {
+    pub max_bucket_size: usize,
+    pub initial_set_capacity: usize,
+    pub ordering: SetOrdering,
+}
@@ -7,13 +7,15 @@ mod configuration;
 mod sorted_set;
 mod supported_term;
 
-use configuration::Configuration;
+use configuration::{Configuration, Direction, SetOrdering};
 use rustler::resource::ResourceArc;
+use rustler::types::binary::{Binary, OwnedBinary};
 use rustler::types::tuple::get_tuple;
 use rustler::{Encoder, Env, NifResult, Term};
 use sorted_set::SortedSet;
+use std::cmp::Ordering;
 use std::sync::Mutex;
-use supported_term::SupportedTerm;
+use supported_term::{SupportedTerm, TermClass, DEFAULT_CLASS_PRIORITY};
 
 mod atoms {
     atoms! {
@@ -35,6 +37,8 @@ mod atoms {
         not_found,
         index_out_of_bounds,
         max_bucket_size_exceeded,
+        bad_format,
+        ordering_mismatch,
     }
 }
 
@@ -68,6 +72,13 @@ pub enum AppendBucketResult {
     MaxBucketSizeExceeded,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SetOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
 fn load(env: Env, _info: Term) -> bool {
     resource!(SortedSetResource, env);
     true
@@ -78,11 +89,17 @@ fn empty<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
     let initial_item_capacity: usize = args[0].decode()?;
     let max_bucket_size: usize = args[1].decode()?;
 
+    let ordering = match decode_ordering(&args[2], &args[3]) {
+        None => return Ok((atoms::error(), atoms::unsupported_type()).encode(env)),
+        Some(ordering) => ordering,
+    };
+
     let initial_set_capacity: usize = (initial_item_capacity / max_bucket_size) + 1;
 
     let configuration = Configuration {
         max_bucket_size,
         initial_set_capacity,
+        ordering,
     };
 
     let resource = ResourceArc::new(SortedSetResource(Mutex::new(SortedSet::empty(
@@ -97,11 +114,17 @@ fn new<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
     let initial_item_capacity: usize = args[0].decode()?;
     let max_bucket_size: usize = args[1].decode()?;
 
+    let ordering = match decode_ordering(&args[2], &args[3]) {
+        None => return Ok((atoms::error(), atoms::unsupported_type()).encode(env)),
+        Some(ordering) => ordering,
+    };
+
     let initial_set_capacity: usize = (initial_item_capacity / max_bucket_size) + 1;
 
     let configuration = Configuration {
         max_bucket_size,
         initial_set_capacity,
+        ordering,
     };
 
     let resource = ResourceArc::new(SortedSetResource(Mutex::new(SortedSet::new(configuration))));
@@ -109,7 +132,57 @@ fn new<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
     Ok((atoms::ok(), resource).encode(env))
 }
 
-#[rustler::nif]
+// Decodes the `empty`/`new` ordering arguments: a `:asc`/`:desc` direction
+// atom, and a list of term-class atoms (`:integer`, `:atom`, `:tuple`,
+// `:list`, `:bitstring`) giving the caller's cross-class priority, or an
+// empty list to keep this type's default priority. Returns `None` on any
+// unrecognized atom, or on a priority list that isn't a permutation of
+// every term class, so the caller can report `:unsupported_type`. A
+// partial list (e.g. `[:tuple, :atom]`) would otherwise leave every
+// omitted class ranked equal to every other omitted class, so two
+// unrelated classes would compare as `Ordering::Equal` and corrupt the
+// binary-search invariant every bucket operation relies on.
+fn decode_ordering(direction_term: &Term, priority_term: &Term) -> Option<SetOrdering> {
+    let direction = match direction_term.atom_to_string().ok()?.as_str() {
+        "asc" => Direction::Ascending,
+        "desc" => Direction::Descending,
+        _ => return None,
+    };
+
+    let priority_terms: Vec<Term> = priority_term.decode().ok()?;
+
+    let class_priority = if priority_terms.is_empty() {
+        DEFAULT_CLASS_PRIORITY.to_vec()
+    } else {
+        let mut classes = Vec::with_capacity(priority_terms.len());
+        for term in priority_terms {
+            classes.push(TermClass::from_str(&term.atom_to_string().ok()?)?);
+        }
+
+        if !is_complete_class_priority(&classes) {
+            return None;
+        }
+
+        classes
+    };
+
+    Some(SetOrdering {
+        direction,
+        class_priority,
+    })
+}
+
+// A caller-supplied priority list is only usable if it ranks every term
+// class exactly once; anything else would leave two or more classes
+// tied for last place.
+fn is_complete_class_priority(classes: &[TermClass]) -> bool {
+    classes.len() == DEFAULT_CLASS_PRIORITY.len()
+        && DEFAULT_CLASS_PRIORITY
+            .iter()
+            .all(|class| classes.contains(class))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
 fn append_bucket<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
     let resource: ResourceArc<SortedSetResource> = match args[0].decode() {
         Err(_) => return Ok((atoms::error(), atoms::bad_reference()).encode(env)),
@@ -180,6 +253,68 @@ fn remove<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
     }
 }
 
+#[rustler::nif(schedule = "DirtyCpu")]
+fn add_all<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
+    let resource: ResourceArc<SortedSetResource> = match args[0].decode() {
+        Err(_) => return Ok((atoms::error(), atoms::bad_reference()).encode(env)),
+        Ok(r) => r,
+    };
+
+    let items: Vec<Term<'a>> = match args[1].decode() {
+        Err(_) => return Ok((atoms::error(), atoms::unsupported_type()).encode(env)),
+        Ok(terms) => terms,
+    };
+
+    let mut set = match resource.0.try_lock() {
+        Err(_) => return Ok((atoms::error(), atoms::lock_fail()).encode(env)),
+        Ok(guard) => guard,
+    };
+
+    let results: Vec<Term<'a>> = items
+        .iter()
+        .map(|term| match convert_to_supported_term(term) {
+            None => (atoms::error(), atoms::unsupported_type()).encode(env),
+            Some(item) => match set.add(item) {
+                AddResult::Added(idx) => (atoms::added(), idx).encode(env),
+                AddResult::Duplicate(idx) => (atoms::duplicate(), idx).encode(env),
+            },
+        })
+        .collect();
+
+    Ok(results.encode(env))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn remove_all<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
+    let resource: ResourceArc<SortedSetResource> = match args[0].decode() {
+        Err(_) => return Ok((atoms::error(), atoms::bad_reference()).encode(env)),
+        Ok(r) => r,
+    };
+
+    let items: Vec<Term<'a>> = match args[1].decode() {
+        Err(_) => return Ok((atoms::error(), atoms::unsupported_type()).encode(env)),
+        Ok(terms) => terms,
+    };
+
+    let mut set = match resource.0.try_lock() {
+        Err(_) => return Ok((atoms::error(), atoms::lock_fail()).encode(env)),
+        Ok(guard) => guard,
+    };
+
+    let results: Vec<Term<'a>> = items
+        .iter()
+        .map(|term| match convert_to_supported_term(term) {
+            None => (atoms::error(), atoms::unsupported_type()).encode(env),
+            Some(item) => match set.remove(&item) {
+                RemoveResult::Removed(idx) => (atoms::removed(), idx).encode(env),
+                RemoveResult::NotFound => atoms::not_found().encode(env),
+            },
+        })
+        .collect();
+
+    Ok(results.encode(env))
+}
+
 #[rustler::nif]
 fn size<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
     let resource: ResourceArc<SortedSetResource> = match args[0].decode() {
@@ -195,7 +330,11 @@ fn size<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
     Ok(set.size().encode(env))
 }
 
-#[rustler::nif]
+// Materializing every element and encoding it as an Erlang list can take
+// well over the ~1ms a normal scheduler can spare on large sets, so the
+// whole NIF runs on a dirty CPU scheduler; decoding the resource happens
+// there too, not on a normal scheduler.
+#[rustler::nif(schedule = "DirtyCpu")]
 fn to_list<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
     let resource: ResourceArc<SortedSetResource> = match args[0].decode() {
         Err(_) => return Ok((atoms::error(), atoms::bad_reference()).encode(env)),
@@ -214,17 +353,25 @@ init! {
     "Elixir.Discord.SortedSet.NifBridge",
     [
         add,
+        add_all,
         append_bucket,
         at,
+        between,
         debug,
+        difference,
+        dump,
         empty,
         empty,
         find_index,
+        intersection,
+        load_set,
         new,
         remove,
+        remove_all,
         size,
         slice,
         to_list,
+        union,
     ],
     load = load
 }
@@ -248,7 +395,9 @@ fn at<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
     }
 }
 
-#[rustler::nif]
+// A large `amount` walks and encodes that many elements, so this is
+// dirty-scheduled for the same reason as `to_list`.
+#[rustler::nif(schedule = "DirtyCpu")]
 fn slice<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
     let resource: ResourceArc<SortedSetResource> = match args[0].decode() {
         Err(_) => return Ok((atoms::error(), atoms::bad_reference()).encode(env)),
@@ -308,6 +457,204 @@ fn debug<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
     Ok((atoms::ok(), set.debug()).encode(env))
 }
 
+#[rustler::nif(schedule = "DirtyCpu")]
+fn dump<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
+    let resource: ResourceArc<SortedSetResource> = match args[0].decode() {
+        Err(_) => return Ok((atoms::error(), atoms::bad_reference()).encode(env)),
+        Ok(r) => r,
+    };
+
+    let set = match resource.0.try_lock() {
+        Err(_) => return Ok((atoms::error(), atoms::lock_fail()).encode(env)),
+        Ok(guard) => guard,
+    };
+
+    let bytes = set.dump();
+    let mut binary = OwnedBinary::new(bytes.len()).expect("allocation failure");
+    binary.as_mut_slice().copy_from_slice(&bytes);
+
+    Ok((atoms::ok(), binary.release(env)).encode(env))
+}
+
+// Named `load` on the Elixir side; the Rust function is named `load_set`
+// to avoid clashing with this module's `on_load` callback, also named
+// `load`.
+#[rustler::nif(name = "load", schedule = "DirtyCpu")]
+fn load_set<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
+    let binary: Binary = match args[0].decode() {
+        Err(_) => return Ok((atoms::error(), atoms::unsupported_type()).encode(env)),
+        Ok(b) => b,
+    };
+
+    match SortedSet::load(binary.as_slice()) {
+        None => Ok((atoms::error(), atoms::bad_format()).encode(env)),
+        Some(set) => {
+            let resource = ResourceArc::new(SortedSetResource(Mutex::new(set)));
+            Ok((atoms::ok(), resource).encode(env))
+        }
+    }
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn between<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
+    let resource: ResourceArc<SortedSetResource> = match args[0].decode() {
+        Err(_) => return Ok((atoms::error(), atoms::bad_reference()).encode(env)),
+        Ok(r) => r,
+    };
+
+    let lower = match convert_to_supported_term(&args[1]) {
+        None => return Ok((atoms::error(), atoms::unsupported_type()).encode(env)),
+        Some(term) => term,
+    };
+    let lower_inclusive: bool = args[2].decode()?;
+
+    let upper = match convert_to_supported_term(&args[3]) {
+        None => return Ok((atoms::error(), atoms::unsupported_type()).encode(env)),
+        Some(term) => term,
+    };
+    let upper_inclusive: bool = args[4].decode()?;
+
+    let set = match resource.0.try_lock() {
+        Err(_) => return Ok((atoms::error(), atoms::lock_fail()).encode(env)),
+        Ok(guard) => guard,
+    };
+
+    Ok(set
+        .range_slice(&lower, lower_inclusive, &upper, upper_inclusive)
+        .encode(env))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn union<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
+    set_op(env, args, SetOp::Union)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn intersection<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
+    set_op(env, args, SetOp::Intersection)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn difference<'a>(env: Env<'a>, args: Vec<Term<'a>>) -> NifResult<Term<'a>> {
+    set_op(env, args, SetOp::Difference)
+}
+
+// Locks both resources in a consistent order (by pointer address) so that
+// two concurrent calls operating on the same pair of sets in opposite
+// order can't deadlock, then merges their already-sorted contents in a
+// single linear walk rather than re-finding every element.
+fn set_op<'a>(env: Env<'a>, args: Vec<Term<'a>>, op: SetOp) -> NifResult<Term<'a>> {
+    let left_resource: ResourceArc<SortedSetResource> = match args[0].decode() {
+        Err(_) => return Ok((atoms::error(), atoms::bad_reference()).encode(env)),
+        Ok(r) => r,
+    };
+    let right_resource: ResourceArc<SortedSetResource> = match args[1].decode() {
+        Err(_) => return Ok((atoms::error(), atoms::bad_reference()).encode(env)),
+        Ok(r) => r,
+    };
+
+    let left_ptr = &left_resource.0 as *const Mutex<SortedSet> as usize;
+    let right_ptr = &right_resource.0 as *const Mutex<SortedSet> as usize;
+
+    let (left_configuration, right_configuration, merged) = if left_ptr <= right_ptr {
+        let left = match left_resource.0.try_lock() {
+            Err(_) => return Ok((atoms::error(), atoms::lock_fail()).encode(env)),
+            Ok(guard) => guard,
+        };
+        let right = match right_resource.0.try_lock() {
+            Err(_) => return Ok((atoms::error(), atoms::lock_fail()).encode(env)),
+            Ok(guard) => guard,
+        };
+        let left_configuration = left.configuration();
+        let right_configuration = right.configuration();
+        let merged = merge(
+            op,
+            &left.to_vec(),
+            &right.to_vec(),
+            &left_configuration.ordering,
+        );
+        (left_configuration, right_configuration, merged)
+    } else {
+        let right = match right_resource.0.try_lock() {
+            Err(_) => return Ok((atoms::error(), atoms::lock_fail()).encode(env)),
+            Ok(guard) => guard,
+        };
+        let left = match left_resource.0.try_lock() {
+            Err(_) => return Ok((atoms::error(), atoms::lock_fail()).encode(env)),
+            Ok(guard) => guard,
+        };
+        let left_configuration = left.configuration();
+        let right_configuration = right.configuration();
+        let merged = merge(
+            op,
+            &left.to_vec(),
+            &right.to_vec(),
+            &left_configuration.ordering,
+        );
+        (left_configuration, right_configuration, merged)
+    };
+
+    // `merge` assumes both operands are sorted under the same comparator
+    // it's given; if the two sets were configured with different
+    // direction/class_priority, `right.to_vec()` comes back sorted under
+    // its own ordering and the two-pointer walk would silently produce a
+    // vector that isn't sorted under either one.
+    if left_configuration.ordering != right_configuration.ordering {
+        return Ok((atoms::error(), atoms::ordering_mismatch()).encode(env));
+    }
+
+    let new_set = SortedSet::from_sorted_vec(left_configuration, merged);
+    let resource = ResourceArc::new(SortedSetResource(Mutex::new(new_set)));
+
+    Ok((atoms::ok(), resource).encode(env))
+}
+
+fn merge(
+    op: SetOp,
+    left: &[SupportedTerm],
+    right: &[SupportedTerm],
+    ordering: &SetOrdering,
+) -> Vec<SupportedTerm> {
+    let mut result = Vec::with_capacity(left.len().max(right.len()));
+    let mut i = 0;
+    let mut j = 0;
+
+    while i < left.len() && j < right.len() {
+        match ordering.compare(&left[i], &right[j]) {
+            Ordering::Less => {
+                if op != SetOp::Intersection {
+                    result.push(left[i].clone());
+                }
+                i += 1;
+            }
+            Ordering::Greater => {
+                if op == SetOp::Union {
+                    result.push(right[j].clone());
+                }
+                j += 1;
+            }
+            Ordering::Equal => {
+                if op != SetOp::Difference {
+                    result.push(left[i].clone());
+                }
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+
+    match op {
+        SetOp::Union => result.extend_from_slice(&right[j..]),
+        SetOp::Difference => {}
+        SetOp::Intersection => {}
+    }
+    if op != SetOp::Intersection {
+        result.extend_from_slice(&left[i..]);
+    }
+
+    result
+}
+
 fn convert_to_supported_term(term: &Term) -> Option<SupportedTerm> {
     if term.is_number() {
         match term.decode() {
@@ -360,3 +707,59 @@ fn convert_to_supported_term(term: &Term) -> Option<SupportedTerm> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_complete_class_priority_accepts_only_full_permutations() {
+        assert!(is_complete_class_priority(&DEFAULT_CLASS_PRIORITY));
+        assert!(is_complete_class_priority(&[
+            TermClass::Tuple,
+            TermClass::Atom,
+            TermClass::Integer,
+            TermClass::List,
+            TermClass::Bitstring,
+        ]));
+
+        // Missing classes.
+        assert!(!is_complete_class_priority(&[
+            TermClass::Tuple,
+            TermClass::Atom,
+        ]));
+
+        // Duplicated class padding out the length.
+        assert!(!is_complete_class_priority(&[
+            TermClass::Integer,
+            TermClass::Integer,
+            TermClass::Atom,
+            TermClass::Tuple,
+            TermClass::List,
+        ]));
+    }
+
+    #[test]
+    fn merge_union_respects_descending_ordering() {
+        let ordering = SetOrdering {
+            direction: Direction::Descending,
+            class_priority: DEFAULT_CLASS_PRIORITY.to_vec(),
+        };
+        let left = vec![
+            SupportedTerm::Integer(5),
+            SupportedTerm::Integer(3),
+            SupportedTerm::Integer(1),
+        ];
+        let right = vec![SupportedTerm::Integer(4), SupportedTerm::Integer(2)];
+
+        let result = merge(SetOp::Union, &left, &right, &ordering);
+
+        assert_eq!(
+            result,
+            vec![5, 4, 3, 2, 1]
+                .into_iter()
+                .map(SupportedTerm::Integer)
+                .collect::<Vec<_>>()
+        );
+    }
+}
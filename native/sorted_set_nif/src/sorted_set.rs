@@ -0,0 +1,460 @@
+use crate::bucket::{Bucket, BucketAddResult, BucketFindResult, BucketRemoveResult};
+use crate::configuration::{Configuration, Direction, SetOrdering};
+use crate::supported_term::{SupportedTerm, TermClass};
+use crate::{AddResult, AppendBucketResult, FindResult, RemoveResult};
+use std::cmp::Ordering;
+
+const DUMP_FORMAT_VERSION: u8 = 2;
+
+pub struct SortedSet {
+    configuration: Configuration,
+    buckets: Vec<Bucket>,
+}
+
+impl SortedSet {
+    pub fn new(configuration: Configuration) -> Self {
+        let max_bucket_size = configuration.max_bucket_size;
+        let mut set = SortedSet::empty(configuration);
+        set.buckets.push(Bucket::new(max_bucket_size));
+        set
+    }
+
+    pub fn empty(configuration: Configuration) -> Self {
+        SortedSet {
+            buckets: Vec::with_capacity(configuration.initial_set_capacity),
+            configuration,
+        }
+    }
+
+    // Builds a set directly from an already-sorted, deduplicated vector by
+    // chunking it into buckets, skipping the per-element `add` walk. Used
+    // by the set-algebra NIFs, whose merge walks already produce output
+    // in order.
+    pub fn from_sorted_vec(configuration: Configuration, items: Vec<SupportedTerm>) -> Self {
+        let bucket_size = configuration.max_bucket_size.max(1);
+        let buckets = items
+            .chunks(bucket_size)
+            .map(|chunk| Bucket::from_vec(chunk.to_vec()))
+            .collect();
+
+        SortedSet {
+            configuration,
+            buckets,
+        }
+    }
+
+    pub fn configuration(&self) -> Configuration {
+        self.configuration.clone()
+    }
+
+    pub fn size(&self) -> usize {
+        self.buckets.iter().map(Bucket::len).sum()
+    }
+
+    pub fn add(&mut self, item: SupportedTerm) -> AddResult {
+        if self.buckets.is_empty() {
+            self.buckets.push(Bucket::new(self.configuration.max_bucket_size));
+        }
+
+        let bucket_idx = self.bucket_index_for(&item);
+        let preceding_len = self.len_before(bucket_idx);
+
+        match self.buckets[bucket_idx].add(item, &self.configuration.ordering) {
+            BucketAddResult::Added(inner_idx) => {
+                if self.buckets[bucket_idx].len() > self.configuration.max_bucket_size {
+                    let new_bucket = self.buckets[bucket_idx].split();
+                    self.buckets.insert(bucket_idx + 1, new_bucket);
+                }
+                AddResult::Added(preceding_len + inner_idx)
+            }
+            BucketAddResult::Duplicate(inner_idx) => {
+                AddResult::Duplicate(preceding_len + inner_idx)
+            }
+        }
+    }
+
+    pub fn remove(&mut self, item: &SupportedTerm) -> RemoveResult {
+        if self.buckets.is_empty() {
+            return RemoveResult::NotFound;
+        }
+
+        let bucket_idx = self.bucket_index_for(item);
+        let preceding_len = self.len_before(bucket_idx);
+
+        match self.buckets[bucket_idx].remove(item, &self.configuration.ordering) {
+            BucketRemoveResult::Removed(inner_idx) => {
+                if self.buckets[bucket_idx].is_empty() {
+                    self.buckets.remove(bucket_idx);
+                }
+                RemoveResult::Removed(preceding_len + inner_idx)
+            }
+            BucketRemoveResult::NotFound => RemoveResult::NotFound,
+        }
+    }
+
+    pub fn find_index(&self, item: &SupportedTerm) -> FindResult {
+        if self.buckets.is_empty() {
+            return FindResult::NotFound;
+        }
+
+        let bucket_idx = self.bucket_index_for(item);
+        let preceding_len = self.len_before(bucket_idx);
+
+        match self.buckets[bucket_idx].find_index(item, &self.configuration.ordering) {
+            BucketFindResult::Found(inner_idx) => FindResult::Found {
+                bucket_idx,
+                inner_idx,
+                idx: preceding_len + inner_idx,
+            },
+            BucketFindResult::NotFound { .. } => FindResult::NotFound,
+        }
+    }
+
+    pub fn at(&self, index: usize) -> Option<SupportedTerm> {
+        let mut remaining = index;
+        for bucket in &self.buckets {
+            if remaining < bucket.len() {
+                return bucket.at(remaining).cloned();
+            }
+            remaining -= bucket.len();
+        }
+        None
+    }
+
+    pub fn slice(&self, start: usize, amount: usize) -> Vec<SupportedTerm> {
+        self.to_vec().into_iter().skip(start).take(amount).collect()
+    }
+
+    // Returns every element `x` with `lower <= x <= upper` by value
+    // (cross-class comparisons use this set's class priority; the
+    // direction does not change which elements qualify), subject to the
+    // inclusivity flags, in this set's storage order. Rather than
+    // scanning the whole set, this locates the first and last buckets
+    // that could hold an element in range by binary search over the
+    // bucket boundaries, then binary-searches within just those two boundary
+    // buckets for the exact start/end, copying the contiguous run in
+    // between untouched.
+    pub fn range_slice(
+        &self,
+        lower: &SupportedTerm,
+        lower_inclusive: bool,
+        upper: &SupportedTerm,
+        upper_inclusive: bool,
+    ) -> Vec<SupportedTerm> {
+        let ordering = &self.configuration.ordering;
+
+        if self.buckets.is_empty()
+            || lower.compare_with_priority(upper, &ordering.class_priority) == Ordering::Greater
+        {
+            return Vec::new();
+        }
+
+        // `lower`/`upper` are always given in natural (value) terms, but
+        // buckets are stored in this set's configured order. For a
+        // descending set that storage order is the reverse of natural
+        // order, so the bound that drives the *start* of the
+        // storage-order scan is `upper`, not `lower` — swap them so the
+        // bucket search below only ever deals in "first in storage" /
+        // "last in storage" terms.
+        let (start_bound, start_inclusive, end_bound, end_inclusive) = match ordering.direction {
+            Direction::Ascending => (lower, lower_inclusive, upper, upper_inclusive),
+            Direction::Descending => (upper, upper_inclusive, lower, lower_inclusive),
+        };
+
+        let start_bucket = match self.buckets.binary_search_by(|bucket| {
+            bucket
+                .last()
+                .map_or(Ordering::Less, |last| ordering.compare(last, start_bound))
+        }) {
+            Ok(idx) => idx,
+            Err(idx) => idx,
+        };
+
+        if start_bucket >= self.buckets.len() {
+            return Vec::new();
+        }
+
+        let end_bucket = match self.buckets.binary_search_by(|bucket| {
+            bucket
+                .first()
+                .map_or(Ordering::Greater, |first| ordering.compare(first, end_bound))
+        }) {
+            Ok(idx) => idx,
+            Err(idx) => match idx.checked_sub(1) {
+                Some(idx) => idx,
+                None => return Vec::new(),
+            },
+        };
+
+        if end_bucket < start_bucket {
+            return Vec::new();
+        }
+
+        let mut result = Vec::new();
+        for bucket_idx in start_bucket..=end_bucket {
+            let items = self.buckets[bucket_idx].to_vec();
+
+            let from = if bucket_idx == start_bucket {
+                items.partition_point(|item| match ordering.compare(item, start_bound) {
+                    Ordering::Less => true,
+                    Ordering::Equal => !start_inclusive,
+                    Ordering::Greater => false,
+                })
+            } else {
+                0
+            };
+
+            let to = if bucket_idx == end_bucket {
+                items.partition_point(|item| match ordering.compare(item, end_bound) {
+                    Ordering::Less => true,
+                    Ordering::Equal => end_inclusive,
+                    Ordering::Greater => false,
+                })
+            } else {
+                items.len()
+            };
+
+            if from < to {
+                result.extend_from_slice(&items[from..to]);
+            }
+        }
+
+        result
+    }
+
+    pub fn to_vec(&self) -> Vec<SupportedTerm> {
+        self.buckets.iter().flat_map(Bucket::to_vec).collect()
+    }
+
+    pub fn append_bucket(&mut self, items: Vec<SupportedTerm>) -> AppendBucketResult {
+        if items.len() > self.configuration.max_bucket_size {
+            return AppendBucketResult::MaxBucketSizeExceeded;
+        }
+
+        self.buckets.push(Bucket::from_vec(items));
+        AppendBucketResult::Ok
+    }
+
+    pub fn debug(&self) -> String {
+        format!("{:?}", self.to_vec())
+    }
+
+    // Serializes the configuration (including ordering) and every
+    // bucket's elements into a compact, versioned binary so `load` can
+    // reconstruct the set in a single O(n) pass with no re-sorting.
+    pub fn dump(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.push(DUMP_FORMAT_VERSION);
+        buf.extend_from_slice(&(self.configuration.max_bucket_size as u64).to_le_bytes());
+        buf.extend_from_slice(&(self.configuration.initial_set_capacity as u64).to_le_bytes());
+
+        buf.push(match self.configuration.ordering.direction {
+            Direction::Ascending => 0,
+            Direction::Descending => 1,
+        });
+        buf.push(self.configuration.ordering.class_priority.len() as u8);
+        for class in &self.configuration.ordering.class_priority {
+            buf.push(class.tag());
+        }
+
+        buf.extend_from_slice(&(self.buckets.len() as u64).to_le_bytes());
+
+        for bucket in &self.buckets {
+            let items = bucket.to_vec();
+            buf.extend_from_slice(&(items.len() as u64).to_le_bytes());
+            for item in &items {
+                item.encode_binary(&mut buf);
+            }
+        }
+
+        buf
+    }
+
+    // The inverse of `dump`. Returns `None` on a version mismatch or a
+    // truncated/corrupt binary rather than panicking.
+    pub fn load(bytes: &[u8]) -> Option<SortedSet> {
+        let mut pos = 0;
+
+        if *bytes.get(pos)? != DUMP_FORMAT_VERSION {
+            return None;
+        }
+        pos += 1;
+
+        let max_bucket_size = read_u64(bytes, &mut pos)? as usize;
+        let initial_set_capacity = read_u64(bytes, &mut pos)? as usize;
+
+        let direction = match *bytes.get(pos)? {
+            0 => Direction::Ascending,
+            1 => Direction::Descending,
+            _ => return None,
+        };
+        pos += 1;
+
+        let priority_len = *bytes.get(pos)? as usize;
+        pos += 1;
+        let mut class_priority = Vec::with_capacity(priority_len);
+        for _ in 0..priority_len {
+            let tag = *bytes.get(pos)?;
+            pos += 1;
+            class_priority.push(TermClass::from_tag(tag)?);
+        }
+
+        let configuration = Configuration {
+            max_bucket_size,
+            initial_set_capacity,
+            ordering: SetOrdering {
+                direction,
+                class_priority,
+            },
+        };
+
+        let bucket_count = read_u64(bytes, &mut pos)? as usize;
+        let mut buckets = Vec::with_capacity(bucket_count.min(bytes.len() - pos));
+        for _ in 0..bucket_count {
+            let item_count = read_u64(bytes, &mut pos)? as usize;
+            let mut items = Vec::with_capacity(item_count.min(bytes.len() - pos));
+            for _ in 0..item_count {
+                items.push(SupportedTerm::decode_binary(bytes, &mut pos)?);
+            }
+            buckets.push(Bucket::from_vec(items));
+        }
+
+        Some(SortedSet {
+            configuration,
+            buckets,
+        })
+    }
+
+    // Locates the bucket whose range should contain `item` under this
+    // set's configured ordering: the first bucket whose last element is
+    // >= item, or the final bucket if `item` sorts after everything we
+    // hold.
+    fn bucket_index_for(&self, item: &SupportedTerm) -> usize {
+        let ordering = &self.configuration.ordering;
+
+        match self.buckets.binary_search_by(|bucket| {
+            bucket
+                .last()
+                .map_or(Ordering::Less, |last| ordering.compare(last, item))
+        }) {
+            Ok(idx) => idx,
+            Err(idx) => idx.min(self.buckets.len() - 1),
+        }
+    }
+
+    fn len_before(&self, bucket_idx: usize) -> usize {
+        self.buckets[..bucket_idx].iter().map(Bucket::len).sum()
+    }
+}
+
+fn read_u64(bytes: &[u8], pos: &mut usize) -> Option<u64> {
+    let value = u64::from_le_bytes(bytes.get(*pos..*pos + 8)?.try_into().ok()?);
+    *pos += 8;
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::supported_term::DEFAULT_CLASS_PRIORITY;
+
+    fn descending_config() -> Configuration {
+        Configuration {
+            max_bucket_size: 3,
+            initial_set_capacity: 1,
+            ordering: SetOrdering {
+                direction: Direction::Descending,
+                class_priority: DEFAULT_CLASS_PRIORITY.to_vec(),
+            },
+        }
+    }
+
+    #[test]
+    fn between_on_a_descending_set_returns_elements_high_to_low() {
+        let mut set = SortedSet::new(descending_config());
+        for value in [3, 1, 4, 1, 5, 9, 2, 6] {
+            set.add(SupportedTerm::Integer(value));
+        }
+
+        let result = set.range_slice(
+            &SupportedTerm::Integer(2),
+            true,
+            &SupportedTerm::Integer(6),
+            true,
+        );
+
+        assert_eq!(
+            result,
+            vec![6, 5, 4, 3, 2]
+                .into_iter()
+                .map(SupportedTerm::Integer)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn removing_the_last_element_of_a_bucket_prunes_it() {
+        let mut set = SortedSet::new(descending_config());
+        set.add(SupportedTerm::Integer(1));
+        set.remove(&SupportedTerm::Integer(1));
+
+        assert_eq!(set.size(), 0);
+        assert_eq!(set.to_vec(), Vec::new());
+    }
+
+    #[test]
+    fn dump_and_load_round_trips_configuration_and_elements() {
+        let mut set = SortedSet::new(descending_config());
+        for value in [3, 1, 4, 1, 5, 9, 2, 6] {
+            set.add(SupportedTerm::Integer(value));
+        }
+
+        let loaded = SortedSet::load(&set.dump()).expect("dump should be loadable");
+
+        assert_eq!(loaded.to_vec(), set.to_vec());
+        assert_eq!(
+            loaded.configuration().ordering.direction,
+            Direction::Descending
+        );
+    }
+
+    #[test]
+    fn dump_and_load_round_trips_non_integer_elements() {
+        let mut set = SortedSet::new(Configuration {
+            max_bucket_size: 3,
+            initial_set_capacity: 1,
+            ordering: SetOrdering::default(),
+        });
+        set.add(SupportedTerm::Atom("ok".to_string()));
+        set.add(SupportedTerm::Tuple(vec![SupportedTerm::Integer(1)]));
+        set.add(SupportedTerm::List(vec![SupportedTerm::Integer(2)]));
+        set.add(SupportedTerm::Bitstring(vec![1, 2, 3]));
+
+        let loaded = SortedSet::load(&set.dump()).expect("dump should be loadable");
+
+        assert_eq!(loaded.to_vec(), set.to_vec());
+    }
+
+    #[test]
+    fn load_rejects_an_empty_buffer() {
+        assert!(SortedSet::load(&[]).is_none());
+    }
+
+    #[test]
+    fn load_rejects_a_mismatched_format_version() {
+        let set = SortedSet::new(descending_config());
+        let mut dump = set.dump();
+        dump[0] = DUMP_FORMAT_VERSION + 1;
+
+        assert!(SortedSet::load(&dump).is_none());
+    }
+
+    #[test]
+    fn load_rejects_a_truncated_buffer() {
+        let mut set = SortedSet::new(descending_config());
+        set.add(SupportedTerm::Integer(1));
+        let dump = set.dump();
+
+        assert!(SortedSet::load(&dump[..dump.len() - 1]).is_none());
+    }
+}
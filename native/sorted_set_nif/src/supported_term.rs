@@ -0,0 +1,309 @@
+use rustler::types::binary::OwnedBinary;
+use rustler::{Encoder, Env, Term};
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SupportedTerm {
+    Integer(i64),
+    Atom(String),
+    Tuple(Vec<SupportedTerm>),
+    List(Vec<SupportedTerm>),
+    Bitstring(Vec<u8>),
+}
+
+// Which variant a `SupportedTerm` belongs to, independent of its value.
+// A `Configuration`'s `SetOrdering` ranks these to decide cross-class
+// comparisons; `DEFAULT_CLASS_PRIORITY` below is the fixed order this
+// type used before orderings became configurable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermClass {
+    Integer,
+    Atom,
+    Tuple,
+    List,
+    Bitstring,
+}
+
+pub const DEFAULT_CLASS_PRIORITY: [TermClass; 5] = [
+    TermClass::Integer,
+    TermClass::Atom,
+    TermClass::Tuple,
+    TermClass::List,
+    TermClass::Bitstring,
+];
+
+impl TermClass {
+    pub fn from_str(s: &str) -> Option<TermClass> {
+        match s {
+            "integer" => Some(TermClass::Integer),
+            "atom" => Some(TermClass::Atom),
+            "tuple" => Some(TermClass::Tuple),
+            "list" => Some(TermClass::List),
+            "bitstring" => Some(TermClass::Bitstring),
+            _ => None,
+        }
+    }
+
+    pub fn tag(&self) -> u8 {
+        match self {
+            TermClass::Integer => 0,
+            TermClass::Atom => 1,
+            TermClass::Tuple => 2,
+            TermClass::List => 3,
+            TermClass::Bitstring => 4,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Option<TermClass> {
+        match tag {
+            0 => Some(TermClass::Integer),
+            1 => Some(TermClass::Atom),
+            2 => Some(TermClass::Tuple),
+            3 => Some(TermClass::List),
+            4 => Some(TermClass::Bitstring),
+            _ => None,
+        }
+    }
+}
+
+impl SupportedTerm {
+    pub fn class(&self) -> TermClass {
+        match self {
+            SupportedTerm::Integer(_) => TermClass::Integer,
+            SupportedTerm::Atom(_) => TermClass::Atom,
+            SupportedTerm::Tuple(_) => TermClass::Tuple,
+            SupportedTerm::List(_) => TermClass::List,
+            SupportedTerm::Bitstring(_) => TermClass::Bitstring,
+        }
+    }
+
+    // Fixed priority among term classes when two terms aren't the same
+    // variant: integers sort first, bitstrings last.
+    fn class_rank(&self) -> u8 {
+        self.class().tag()
+    }
+
+    // Compares two terms under a caller-chosen priority among term
+    // classes, falling back to this type's natural per-variant Ord when
+    // both terms belong to the same class.
+    pub fn compare_with_priority(&self, other: &Self, priority: &[TermClass]) -> Ordering {
+        if self.class() == other.class() {
+            self.cmp(other)
+        } else {
+            let rank = |class: TermClass| {
+                priority
+                    .iter()
+                    .position(|candidate| *candidate == class)
+                    .unwrap_or(priority.len())
+            };
+            rank(self.class()).cmp(&rank(other.class()))
+        }
+    }
+}
+
+impl PartialOrd for SupportedTerm {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SupportedTerm {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (SupportedTerm::Integer(a), SupportedTerm::Integer(b)) => a.cmp(b),
+            (SupportedTerm::Atom(a), SupportedTerm::Atom(b)) => a.cmp(b),
+            (SupportedTerm::Tuple(a), SupportedTerm::Tuple(b)) => a.cmp(b),
+            (SupportedTerm::List(a), SupportedTerm::List(b)) => a.cmp(b),
+            (SupportedTerm::Bitstring(a), SupportedTerm::Bitstring(b)) => a.cmp(b),
+            (a, b) => a.class_rank().cmp(&b.class_rank()),
+        }
+    }
+}
+
+// Erlang/Elixir atoms are capped at 255 bytes; a dump blob claiming a
+// longer atom is corrupt or from an incompatible format, and decoding it
+// anyway would only defer the failure to the next time this term is
+// encoded back out, panicking inside `Atom::from_str` instead of
+// surfacing `:bad_format` up front.
+const MAX_ATOM_BYTES: usize = 255;
+
+// Tags for the versioned binary encoding used by `dump`/`load`.
+const TAG_INTEGER: u8 = 0;
+const TAG_ATOM: u8 = 1;
+const TAG_TUPLE: u8 = 2;
+const TAG_LIST: u8 = 3;
+const TAG_BITSTRING: u8 = 4;
+
+impl SupportedTerm {
+    pub fn encode_binary(&self, buf: &mut Vec<u8>) {
+        match self {
+            SupportedTerm::Integer(i) => {
+                buf.push(TAG_INTEGER);
+                buf.extend_from_slice(&i.to_le_bytes());
+            }
+            SupportedTerm::Atom(a) => {
+                buf.push(TAG_ATOM);
+                buf.extend_from_slice(&(a.len() as u32).to_le_bytes());
+                buf.extend_from_slice(a.as_bytes());
+            }
+            SupportedTerm::Tuple(items) => {
+                buf.push(TAG_TUPLE);
+                buf.extend_from_slice(&(items.len() as u32).to_le_bytes());
+                for item in items {
+                    item.encode_binary(buf);
+                }
+            }
+            SupportedTerm::List(items) => {
+                buf.push(TAG_LIST);
+                buf.extend_from_slice(&(items.len() as u32).to_le_bytes());
+                for item in items {
+                    item.encode_binary(buf);
+                }
+            }
+            SupportedTerm::Bitstring(bytes) => {
+                buf.push(TAG_BITSTRING);
+                buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+                buf.extend_from_slice(bytes);
+            }
+        }
+    }
+
+    pub fn decode_binary(bytes: &[u8], pos: &mut usize) -> Option<SupportedTerm> {
+        let tag = *bytes.get(*pos)?;
+        *pos += 1;
+
+        match tag {
+            TAG_INTEGER => {
+                let value = i64::from_le_bytes(bytes.get(*pos..*pos + 8)?.try_into().ok()?);
+                *pos += 8;
+                Some(SupportedTerm::Integer(value))
+            }
+            TAG_ATOM => {
+                let len = read_u32(bytes, pos)? as usize;
+                if len > MAX_ATOM_BYTES {
+                    return None;
+                }
+                let slice = bytes.get(*pos..*pos + len)?;
+                *pos += len;
+                Some(SupportedTerm::Atom(String::from_utf8(slice.to_vec()).ok()?))
+            }
+            TAG_TUPLE => {
+                let count = read_u32(bytes, pos)? as usize;
+                let mut items = Vec::with_capacity(count.min(bytes.len() - *pos));
+                for _ in 0..count {
+                    items.push(SupportedTerm::decode_binary(bytes, pos)?);
+                }
+                Some(SupportedTerm::Tuple(items))
+            }
+            TAG_LIST => {
+                let count = read_u32(bytes, pos)? as usize;
+                let mut items = Vec::with_capacity(count.min(bytes.len() - *pos));
+                for _ in 0..count {
+                    items.push(SupportedTerm::decode_binary(bytes, pos)?);
+                }
+                Some(SupportedTerm::List(items))
+            }
+            TAG_BITSTRING => {
+                let len = read_u32(bytes, pos)? as usize;
+                let slice = bytes.get(*pos..*pos + len)?;
+                *pos += len;
+                Some(SupportedTerm::Bitstring(slice.to_vec()))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8], pos: &mut usize) -> Option<u32> {
+    let value = u32::from_le_bytes(bytes.get(*pos..*pos + 4)?.try_into().ok()?);
+    *pos += 4;
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_with_priority_ranks_by_class_when_variants_differ() {
+        let integer = SupportedTerm::Integer(1);
+        let atom = SupportedTerm::Atom("a".to_string());
+
+        assert_eq!(
+            integer.compare_with_priority(&atom, &DEFAULT_CLASS_PRIORITY),
+            Ordering::Less
+        );
+
+        let priority = [TermClass::Atom, TermClass::Integer, TermClass::Tuple, TermClass::List, TermClass::Bitstring];
+        assert_eq!(
+            integer.compare_with_priority(&atom, &priority),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn compare_with_priority_falls_back_to_natural_order_within_a_class() {
+        let a = SupportedTerm::Integer(1);
+        let b = SupportedTerm::Integer(2);
+
+        assert_eq!(
+            a.compare_with_priority(&b, &DEFAULT_CLASS_PRIORITY),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn encode_binary_and_decode_binary_round_trip_every_variant() {
+        let terms = vec![
+            SupportedTerm::Integer(-7),
+            SupportedTerm::Atom("ok".to_string()),
+            SupportedTerm::Tuple(vec![SupportedTerm::Integer(1), SupportedTerm::Integer(2)]),
+            SupportedTerm::List(vec![SupportedTerm::Atom("a".to_string())]),
+            SupportedTerm::Bitstring(vec![1, 2, 3]),
+        ];
+
+        for term in terms {
+            let mut buf = Vec::new();
+            term.encode_binary(&mut buf);
+
+            let mut pos = 0;
+            assert_eq!(SupportedTerm::decode_binary(&buf, &mut pos), Some(term));
+            assert_eq!(pos, buf.len());
+        }
+    }
+
+    #[test]
+    fn decode_binary_rejects_an_atom_over_the_erlang_atom_length_cap() {
+        let mut buf = Vec::new();
+        buf.push(TAG_ATOM);
+        let oversized_len = (MAX_ATOM_BYTES + 1) as u32;
+        buf.extend_from_slice(&oversized_len.to_le_bytes());
+        buf.extend(std::iter::repeat(b'a').take(MAX_ATOM_BYTES + 1));
+
+        let mut pos = 0;
+        assert_eq!(SupportedTerm::decode_binary(&buf, &mut pos), None);
+    }
+}
+
+impl Encoder for SupportedTerm {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self {
+            SupportedTerm::Integer(i) => i.encode(env),
+            SupportedTerm::Atom(a) => {
+                rustler::types::atom::Atom::from_str(env, a)
+                    .expect("supported atoms are valid Elixir atoms")
+                    .encode(env)
+            }
+            SupportedTerm::Tuple(inner) => {
+                let terms: Vec<Term<'a>> = inner.iter().map(|term| term.encode(env)).collect();
+                rustler::types::tuple::make_tuple(env, &terms)
+            }
+            SupportedTerm::List(inner) => inner.encode(env),
+            SupportedTerm::Bitstring(bytes) => {
+                let mut binary = OwnedBinary::new(bytes.len()).expect("allocation failure");
+                binary.as_mut_slice().copy_from_slice(bytes);
+                binary.release(env).encode(env)
+            }
+        }
+    }
+}